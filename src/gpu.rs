@@ -2,12 +2,8 @@ use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
 
-use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::render::Texture;
-use sdl2::video::Window;
-
 use crate::memory::Memory;
+use crate::screen::Screen;
 
 const BYTES_PER_PIXEL: u8 = 4; // RGBA8888
 const BUFFER_HEIGHT: u16 = 256;
@@ -16,14 +12,105 @@ const BUFFER_WIDTH: u16 = 256;
 const BUFFER_SIZE: usize =
     BUFFER_HEIGHT as usize * BUFFER_WIDTH as usize * BYTES_PER_PIXEL as usize;
 
+const VISIBLE_WIDTH: u32 = 160;
+const VISIBLE_HEIGHT: u32 = 144;
+
+// Interrupt Flag register.
+const IF_ADDR: usize = 0xff0f;
+const IF_VBLANK: u8 = 1 << 0;
+const IF_LCD_STAT: u8 = 1 << 1;
+
+// STAT register (0xff41) bits.
+const STAT_MODE_MASK: u8 = 0b0000_0011;
+const STAT_COINCIDENCE_FLAG: u8 = 1 << 2;
+const STAT_HBLANK_INT: u8 = 1 << 3;
+const STAT_VBLANK_INT: u8 = 1 << 4;
+const STAT_OAM_INT: u8 = 1 << 5;
+const STAT_COINCIDENCE_INT: u8 = 1 << 6;
+
+// LCDC register (0xff40) bits.
+const LCDC_BG_WINDOW_ENABLE: u8 = 1 << 0;
+const LCDC_BG_TILE_MAP: u8 = 1 << 3;
+const LCDC_OBJ_ENABLE: u8 = 1 << 1;
+const LCDC_OBJ_SIZE: u8 = 1 << 2;
+const LCDC_TILE_DATA: u8 = 1 << 4;
+const LCDC_WINDOW_ENABLE: u8 = 1 << 5;
+const LCDC_WINDOW_TILE_MAP: u8 = 1 << 6;
+const LCDC_LCD_ENABLE: u8 = 1 << 7;
+
+// OAM (0xfe00-0xfe9f) attribute byte bits.
+const OAM_PALETTE: u8 = 1 << 4;
+const OAM_FLIP_X: u8 = 1 << 5;
+const OAM_FLIP_Y: u8 = 1 << 6;
+const OAM_BG_PRIORITY: u8 = 1 << 7;
+
+const OAM_ADDR: usize = 0xfe00;
+const MAX_SPRITES_PER_LINE: usize = 10;
+
+// Dot (PPU clock cycle) budgets for a single scanline, following real DMG
+// timing: 80 dots of OAM search, ~172 of pixel transfer, and the remainder
+// of the 456 dots/line spent in HBlank.
+const OAM_SEARCH_DOTS: u32 = 80;
+const PIXEL_TRANSFER_DOTS: u32 = 172;
+const SCANLINE_DOTS: u32 = 456;
+const HBLANK_DOTS: u32 = SCANLINE_DOTS - OAM_SEARCH_DOTS - PIXEL_TRANSFER_DOTS;
+
+// Total dots in a full frame (154 scanlines), used to pace the blank frame
+// presented while the LCD is disabled at the same cadence as a real frame.
+const FRAME_DOTS: u32 = SCANLINE_DOTS * 154;
+
+// The four PPU modes, as reported in the low two bits of STAT.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    HBlank = 0,
+    VBlank = 1,
+    OamSearch = 2,
+    PixelTransfer = 3,
+}
+
+impl Mode {
+    fn stat_interrupt_bit(self) -> u8 {
+        match self {
+            Mode::HBlank => STAT_HBLANK_INT,
+            Mode::VBlank => STAT_VBLANK_INT,
+            Mode::OamSearch => STAT_OAM_INT,
+            Mode::PixelTransfer => 0,
+        }
+    }
+}
+
+// The two ways LCDC bit 4 lets tile numbers address tile RAM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TileDataMode {
+    // Tile number is an unsigned offset from 0x8000. Always used for OBJ.
+    Unsigned8000,
+    // Tile number is a *signed* offset from a 0x9000 base.
+    Signed8800,
+}
+
+impl TileDataMode {
+    fn from_lcdc(lcdc: u8) -> Self {
+        if lcdc & LCDC_TILE_DATA != 0 {
+            TileDataMode::Unsigned8000
+        } else {
+            TileDataMode::Signed8800
+        }
+    }
+}
+
 pub struct Buffer {
     pub buffer: [u8; BUFFER_SIZE],
+
+    // The raw (pre-palette) BG/window color index, 0-3, for every pixel in
+    // `buffer`. Sprites consult this to implement the behind-BG priority bit.
+    pub bg_index: [u8; BUFFER_WIDTH as usize * BUFFER_HEIGHT as usize],
 }
 
 impl Buffer {
     pub const fn new() -> Self {
         Self {
             buffer: [0; BUFFER_SIZE],
+            bg_index: [0; BUFFER_WIDTH as usize * BUFFER_HEIGHT as usize],
         }
     }
 }
@@ -39,6 +126,19 @@ pub struct Gpu {
     // Writing will reset the counter.
     pub ly: u8,
 
+    // The LY compare register (0xff45).
+    //
+    // Whenever `ly` equals `lyc` the STAT coincidence flag is set and,
+    // if enabled, the LCD STAT interrupt is requested.
+    pub lyc: u8,
+
+    // The LCD status register (0xff41).
+    //
+    // Bits 0-1 mirror the current mode, bit 2 is the LY==LYC coincidence
+    // flag, and bits 3-6 enable the STAT interrupt for HBlank, VBlank,
+    // OAM search and coincidence respectively.
+    pub stat: u8,
+
     // The Y position in the 256x256 pixels BG map (32x32 tiles)
     // which is to be displayed at the upper/left LCD display position.
     pub scy: u8,
@@ -46,6 +146,46 @@ pub struct Gpu {
     // The X position in the 256x256 pixels BG map (32x32 tiles)
     // which is to be displayed at the upper/left LCD display position.
     pub scx: u8,
+
+    // The LCD control register (0xff40).
+    pub lcdc: u8,
+
+    // Object palette 0 and 1 (0xff48/0xff49), selected per-sprite by
+    // attribute bit 4.
+    pub obp0: u8,
+    pub obp1: u8,
+
+    // Window Y position (0xff4a): the screen scanline at which the window
+    // starts being drawn, once enabled via LCDC bit 5.
+    pub wy: u8,
+
+    // Window X position plus 7 (0xff4b): `wx - 7` is the screen column at
+    // which the window starts being drawn.
+    pub wx: u8,
+
+    // BG/window palette (0xff47): packs four 2-bit shades, one per BG/window
+    // color index.
+    pub bgp: u8,
+
+    // The RGBA color shown for each of the four DMG shades (0 = lightest,
+    // 3 = darkest), after the BGP/OBP0/OBP1 lookup. Defaults to the classic
+    // green DMG LCD tint; swap in grayscale or any other 4-color set.
+    pub palette: [(u8, u8, u8, u8); 4],
+
+    // Precomputed gamma/contrast curve applied to each output channel so
+    // colors look like a washed-out LCD rather than raw flat shades.
+    color_lut: [u8; 256],
+
+    // The current PPU mode, driven by `dots`.
+    mode: Mode,
+
+    // Dots (PPU clock cycles) accumulated in the current mode.
+    dots: u32,
+
+    // The window's internal line counter. Unlike `ly`, this only advances
+    // on scanlines where a window pixel was actually emitted, so an
+    // off-screen or mid-frame-disabled window doesn't skip rows of its map.
+    window_current_y: u8,
 }
 
 impl Gpu {
@@ -53,57 +193,404 @@ impl Gpu {
         Self {
             memory,
             ly: 0,
+            lyc: 0,
+            stat: Mode::OamSearch as u8,
             scy: 0,
             scx: 0,
+            lcdc: 0,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+            bgp: 0,
+            palette: DMG_GREEN_PALETTE,
+            color_lut: build_color_lut(),
+            mode: Mode::OamSearch,
+            dots: 0,
+            window_current_y: 0,
         }
     }
 
-    pub fn display(
-        &mut self,
-        canvas: &mut Canvas<Window>,
-        texture: &mut Texture,
-        buffer: &mut Buffer,
-    ) {
-        if self.ly == 0 {
-            let memory = self.memory.borrow();
+    // Looks a shade (0-3) up in `palette` and runs it through the gamma LUT.
+    fn shade_to_rgba(&self, shade: u8) -> (u8, u8, u8, u8) {
+        let (r, g, b, a) = self.palette[shade as usize];
+        (
+            self.color_lut[r as usize],
+            self.color_lut[g as usize],
+            self.color_lut[b as usize],
+            a,
+        )
+    }
+
+    // Advances the PPU by `cycles` dots, driving the mode state machine and
+    // pushing/presenting a frame through `screen` as the relevant modes are
+    // reached.
+    pub fn display(&mut self, cycles: u8, screen: &mut dyn Screen, buffer: &mut Buffer) {
+        if self.lcdc & LCDC_LCD_ENABLE == 0 {
+            self.ly = 0;
+            self.mode = Mode::OamSearch;
+            // A real DMG reports STAT mode 0 (and no coincidence) while the
+            // LCD is off, regardless of what the mode state machine was
+            // doing when it was switched off.
+            self.stat &= !(STAT_MODE_MASK | STAT_COINCIDENCE_FLAG);
+
+            self.dots += u32::from(cycles);
+            if self.dots < FRAME_DOTS {
+                return;
+            }
+            self.dots -= FRAME_DOTS;
+
+            self.blank_frame(buffer);
+            self.present_visible_frame(screen, buffer);
+
+            return;
+        }
+
+        self.dots += u32::from(cycles);
+
+        loop {
+            let budget = match self.mode {
+                Mode::OamSearch => OAM_SEARCH_DOTS,
+                Mode::PixelTransfer => PIXEL_TRANSFER_DOTS,
+                Mode::HBlank => HBLANK_DOTS,
+                Mode::VBlank => SCANLINE_DOTS,
+            };
+
+            if self.dots < budget {
+                break;
+            }
+
+            self.dots -= budget;
+            self.advance_mode(screen, buffer);
+        }
+    }
+
+    fn advance_mode(&mut self, screen: &mut dyn Screen, buffer: &mut Buffer) {
+        match self.mode {
+            Mode::OamSearch => self.set_mode(Mode::PixelTransfer),
+
+            Mode::PixelTransfer => {
+                if self.ly == 0 {
+                    self.draw_bg_map(buffer);
+                }
+                if self.ly < 144 {
+                    self.render_window(self.ly, buffer);
+                    self.render_sprites(self.ly, buffer);
+                }
+                self.set_mode(Mode::HBlank);
+            }
+
+            Mode::HBlank => {
+                self.ly = self.ly.wrapping_add(1);
+
+                if self.ly == 144 {
+                    self.present_visible_frame(screen, buffer);
+
+                    self.set_mode(Mode::VBlank);
+                    self.request_interrupt(IF_VBLANK);
+                } else {
+                    self.set_mode(Mode::OamSearch);
+                }
+            }
+
+            Mode::VBlank => {
+                self.ly = self.ly.wrapping_add(1);
+
+                if self.ly > 153 {
+                    self.ly = 0;
+                    self.set_mode(Mode::OamSearch);
+                } else {
+                    self.update_stat();
+                }
+            }
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.update_stat();
+
+        let int_bit = mode.stat_interrupt_bit();
+        if int_bit != 0 && self.stat & int_bit != 0 {
+            self.request_interrupt(IF_LCD_STAT);
+        }
+    }
+
+    fn update_stat(&mut self) {
+        self.stat = (self.stat & !STAT_MODE_MASK) | (self.mode as u8 & STAT_MODE_MASK);
+
+        if self.ly == self.lyc {
+            self.stat |= STAT_COINCIDENCE_FLAG;
+            if self.stat & STAT_COINCIDENCE_INT != 0 {
+                self.request_interrupt(IF_LCD_STAT);
+            }
+        } else {
+            self.stat &= !STAT_COINCIDENCE_FLAG;
+        }
+    }
+
+    // Crops the visible 160x144 window out of the BG map buffer (offset by
+    // `scy`/`scx`, each wrapping around the 256x256 map) and pushes it to
+    // `screen`.
+    fn present_visible_frame(&self, screen: &mut dyn Screen, buffer: &Buffer) {
+        let row_bytes = VISIBLE_WIDTH as usize * BYTES_PER_PIXEL as usize;
+        let mut frame = vec![0u8; row_bytes * VISIBLE_HEIGHT as usize];
+
+        for row in 0..VISIBLE_HEIGHT as usize {
+            let map_row = self.scy.wrapping_add(row as u8) as usize;
+            let dst_row_start = row * row_bytes;
+
+            for col in 0..VISIBLE_WIDTH as usize {
+                let map_col = self.scx.wrapping_add(col as u8) as usize;
+                let src_pixel = map_row * BUFFER_WIDTH as usize + map_col;
+                let src_start = src_pixel * BYTES_PER_PIXEL as usize;
+                let dst_start = dst_row_start + col * BYTES_PER_PIXEL as usize;
+
+                frame[dst_start..dst_start + BYTES_PER_PIXEL as usize]
+                    .copy_from_slice(&buffer.buffer[src_start..src_start + BYTES_PER_PIXEL as usize]);
+            }
+        }
+
+        screen.put_frame(&frame, VISIBLE_WIDTH, VISIBLE_HEIGHT);
+        screen.present();
+    }
+
+    fn request_interrupt(&self, bit: u8) {
+        let mut memory = self.memory.borrow_mut();
+        let flags = memory.load(IF_ADDR);
+        memory.store(IF_ADDR, flags | bit);
+    }
+
+    fn draw_bg_map(&self, buffer: &mut Buffer) {
+        if self.lcdc & LCDC_BG_WINDOW_ENABLE == 0 {
+            self.blank_frame(buffer);
+            return;
+        }
+
+        let map_base: usize = if self.lcdc & LCDC_BG_TILE_MAP != 0 {
+            0x9c00
+        } else {
+            0x9800
+        };
+        let addressing = TileDataMode::from_lcdc(self.lcdc);
+
+        let memory = self.memory.borrow();
+
+        let mut tile_x: u8;
+        let mut tile_y: u8;
+
+        for i in 0..1024usize {
+            let tile_num = memory.load(map_base + i);
+
+            tile_x = (i % 32) as u8;
+            tile_y = (i / 32) as u8;
+
+            self.print_tile(self.get_tile(tile_num, addressing), buffer, tile_x, tile_y);
+        }
+    }
+
+    // Fills the whole BG map buffer with white, e.g. while the LCD or the
+    // BG/window layer is disabled via LCDC.
+    fn blank_frame(&self, buffer: &mut Buffer) {
+        for chunk in buffer.buffer.chunks_exact_mut(BYTES_PER_PIXEL as usize) {
+            chunk.copy_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+        }
+        buffer.bg_index = [0; BUFFER_WIDTH as usize * BUFFER_HEIGHT as usize];
+    }
 
-            let mut tile_x: u8;
-            let mut tile_y: u8;
+    // Draws the window layer's contribution to scanline `ly`, if enabled and
+    // in range, advancing `window_current_y` only when a pixel was emitted.
+    fn render_window(&mut self, ly: u8, buffer: &mut Buffer) {
+        if ly == 0 {
+            self.window_current_y = 0;
+        }
+
+        if self.lcdc & LCDC_BG_WINDOW_ENABLE == 0 || self.lcdc & LCDC_WINDOW_ENABLE == 0 || ly < self.wy {
+            return;
+        }
+
+        let window_start_x = i16::from(self.wx) - 7;
+        if window_start_x >= 160 {
+            return;
+        }
 
-            // BG Map Data 1
-            for (i, tile_addr) in (0x9800..=0x9bff).enumerate() {
-                let tile_num = memory.load(tile_addr);
+        let map_base: usize = if self.lcdc & LCDC_WINDOW_TILE_MAP != 0 {
+            0x9c00
+        } else {
+            0x9800
+        };
+        let addressing = TileDataMode::from_lcdc(self.lcdc);
+        let window_row = self.window_current_y;
+        let mut drew_any = false;
 
-                tile_x = (i % 32) as u8;
-                tile_y = (i / 32) as u8;
+        let memory = self.memory.borrow();
 
-                self.print_tile(self.get_tile(tile_num), &mut buffer.buffer, tile_x, tile_y);
+        for screen_x in 0..160i16 {
+            let window_col = screen_x - window_start_x;
+            if window_col < 0 {
+                continue;
             }
-            texture
-                .update(
-                    None,
-                    &buffer.buffer,
-                    BUFFER_WIDTH as usize * BYTES_PER_PIXEL as usize,
-                )
-                .unwrap();
+            let window_col = window_col as u16;
+
+            let tile_col = (window_col / 8) % 32;
+            let tile_row = u16::from(window_row / 8);
+            let tile_num = memory.load(map_base + (tile_row * 32 + tile_col) as usize);
+            let tile_bytes = self.get_tile(tile_num, addressing);
+
+            let pixel_col_in_tile = (window_col % 8) as u8;
+            let pixel_row_in_tile = window_row % 8;
+            let color_index = tile_pixel_index(&tile_bytes, pixel_row_in_tile, pixel_col_in_tile);
+            let shade = palette_shade(self.bgp, color_index);
+            let color = self.shade_to_rgba(shade);
+
+            let map_x = self.scx.wrapping_add(screen_x as u8) as usize;
+            let map_y = self.scy.wrapping_add(ly) as usize;
+            let pixel = map_y * BUFFER_WIDTH as usize + map_x;
+            let buf_index = pixel * BYTES_PER_PIXEL as usize;
+
+            buffer.buffer[buf_index] = color.0;
+            buffer.buffer[buf_index + 1] = color.1;
+            buffer.buffer[buf_index + 2] = color.2;
+            buffer.buffer[buf_index + 3] = color.3;
+            buffer.bg_index[pixel] = color_index;
+
+            drew_any = true;
+        }
+
+        if drew_any {
+            self.window_current_y = self.window_current_y.wrapping_add(1);
+        }
+    }
+
+    // Selects up to `MAX_SPRITES_PER_LINE` OAM entries visible on `ly`, in
+    // OAM scan order, then composites them onto the BG map row for that
+    // scanline in DMG priority order (lowest X, then lowest OAM index, wins).
+    fn render_sprites(&self, ly: u8, buffer: &mut Buffer) {
+        if self.lcdc & LCDC_OBJ_ENABLE == 0 {
+            return;
         }
 
-        // VBlank
-        if self.ly == 144 {
-            let scanline_src = Rect::new(0, self.scy as i32, 160, 144);
+        struct Sprite {
+            oam_index: u8,
+            x: u8,
+            y: u8,
+            tile: u8,
+            attrs: u8,
+        }
 
-            canvas.copy(&texture, scanline_src, None).unwrap();
+        let tall = self.lcdc & LCDC_OBJ_SIZE != 0;
+        let height: i16 = if tall { 16 } else { 8 };
 
-            canvas.present();
+        let mut visible: Vec<Sprite> = Vec::with_capacity(MAX_SPRITES_PER_LINE);
+        {
+            let memory = self.memory.borrow();
+
+            for oam_index in 0..40u16 {
+                if visible.len() == MAX_SPRITES_PER_LINE {
+                    break;
+                }
+
+                let entry = OAM_ADDR + oam_index as usize * 4;
+                let y = memory.load(entry);
+                let sprite_top = i16::from(y) - 16;
+
+                if i16::from(ly) < sprite_top || i16::from(ly) >= sprite_top + height {
+                    continue;
+                }
+
+                let x = memory.load(entry + 1);
+                let mut tile = memory.load(entry + 2);
+                if tall {
+                    tile &= 0xfe;
+                }
+                let attrs = memory.load(entry + 3);
+
+                visible.push(Sprite {
+                    oam_index: oam_index as u8,
+                    x,
+                    y,
+                    tile,
+                    attrs,
+                });
+            }
         }
 
-        self.ly = self.ly.wrapping_add(1);
+        visible.sort_by_key(|s| (s.x, s.oam_index));
+
+        let mut drawn = [false; 160];
+
+        for sprite in &visible {
+            let sprite_top = i16::from(sprite.y) - 16;
+            let mut row_in_sprite = i16::from(ly) - sprite_top;
+            if sprite.attrs & OAM_FLIP_Y != 0 {
+                row_in_sprite = height - 1 - row_in_sprite;
+            }
+
+            let tile_num = if row_in_sprite >= 8 {
+                sprite.tile | 1
+            } else {
+                sprite.tile
+            };
+            let row_in_tile = (row_in_sprite % 8) as u8;
+            let tile_bytes = self.get_tile(tile_num, TileDataMode::Unsigned8000);
+
+            let palette = if sprite.attrs & OAM_PALETTE != 0 {
+                self.obp1
+            } else {
+                self.obp0
+            };
+            let behind_bg = sprite.attrs & OAM_BG_PRIORITY != 0;
+            let flip_x = sprite.attrs & OAM_FLIP_X != 0;
+
+            for col in 0..8u8 {
+                let sample_col = if flip_x { 7 - col } else { col };
+                let color_index = tile_pixel_index(&tile_bytes, row_in_tile, sample_col);
+                if color_index == 0 {
+                    continue;
+                }
+
+                let screen_x = i16::from(sprite.x) - 8 + i16::from(col);
+                if screen_x < 0 || screen_x >= 160 {
+                    continue;
+                }
+                let screen_x = screen_x as usize;
+
+                if drawn[screen_x] {
+                    continue;
+                }
+
+                let map_x = self.scx.wrapping_add(screen_x as u8) as usize;
+                let map_y = self.scy.wrapping_add(ly) as usize;
+                let pixel = map_y * BUFFER_WIDTH as usize + map_x;
+
+                if behind_bg && buffer.bg_index[pixel] != 0 {
+                    continue;
+                }
+
+                let shade = palette_shade(palette, color_index);
+                let color = self.shade_to_rgba(shade);
+
+                let buf_index = pixel * BYTES_PER_PIXEL as usize;
+                buffer.buffer[buf_index] = color.0;
+                buffer.buffer[buf_index + 1] = color.1;
+                buffer.buffer[buf_index + 2] = color.2;
+                buffer.buffer[buf_index + 3] = color.3;
+
+                drawn[screen_x] = true;
+            }
+        }
     }
 
-    fn get_tile(&self, tile_num: u8) -> [u8; 16] {
+    fn get_tile(&self, tile_num: u8, addressing: TileDataMode) -> [u8; 16] {
         let memory = self.memory.borrow();
 
-        let tile_start = 0x8000 + u16::from(tile_num) * 16;
+        let tile_start = match addressing {
+            TileDataMode::Unsigned8000 => 0x8000 + u16::from(tile_num) * 16,
+            TileDataMode::Signed8800 => {
+                let signed_offset = i16::from(tile_num as i8);
+                (0x9000i32 + i32::from(signed_offset) * 16) as u16
+            }
+        };
         let tile_end = tile_start + 16;
 
         let mut tile: [u8; 16] = [0; 16];
@@ -118,33 +605,29 @@ impl Gpu {
         tile
     }
 
-    fn print_tile(&self, tile: [u8; 16], buffer: &mut [u8], x: u8, y: u8) {
+    fn print_tile(&self, tile: [u8; 16], buffer: &mut Buffer, x: u8, y: u8) {
         assert!(x < 32);
         assert!(y < 32);
 
-        let mut xx;
-        let mut yy;
-        for row in 0..=7 {
-            let b = (tile[row * 2], tile[1 + row * 2]);
+        for row in 0..8u8 {
+            for col in 0..8u8 {
+                let color_index = tile_pixel_index(&tile, row, col);
+                let shade = palette_shade(self.bgp, color_index);
+                let color = self.shade_to_rgba(shade);
 
-            for col in (0..=7).rev() {
-                let color = if (b.0 & (1 << col)).count_ones() == 0 {
-                    (0xff, 0xff, 0xff, 0xff)
-                } else {
-                    (0xff, 0x00, 0x00, 0x00)
-                };
+                let xx = x as usize * 8 + col as usize;
+                let yy = y as usize * 8 + row as usize;
 
-                xx = x as i32 * 8 + (col as i8 - 7).abs() as i32;
-                yy = (y as i32 * 8) + row as i32;
-
-                let index =
-                    (xx as usize + yy as usize * BUFFER_WIDTH as usize) * BYTES_PER_PIXEL as usize;
+                let pixel = xx + yy * BUFFER_WIDTH as usize;
+                let index = pixel * BYTES_PER_PIXEL as usize;
 
                 // 4 bytes per pixel
-                buffer[index] = color.0;
-                buffer[index + 1] = color.1;
-                buffer[index + 2] = color.2;
-                buffer[index + 3] = color.3;
+                buffer.buffer[index] = color.0;
+                buffer.buffer[index + 1] = color.1;
+                buffer.buffer[index + 2] = color.2;
+                buffer.buffer[index + 3] = color.3;
+
+                buffer.bg_index[pixel] = color_index;
             }
         }
     }
@@ -155,3 +638,242 @@ impl<'a> fmt::Display for Gpu {
         write!(f, "abc")
     }
 }
+
+// Combines the two bitplane bytes of a tile row into a 0-3 color index for
+// the given column (0 = leftmost pixel). Shared by every tile consumer (BG,
+// window and sprites), as is `palette_shade` below.
+fn tile_pixel_index(tile: &[u8; 16], row: u8, col: u8) -> u8 {
+    let lo = tile[row as usize * 2];
+    let hi = tile[row as usize * 2 + 1];
+    let bit = 7 - col;
+
+    (((hi >> bit) & 1) << 1) | ((lo >> bit) & 1)
+}
+
+// Extracts the 2-bit shade for a color index (0-3) from a packed DMG
+// palette register (BGP/OBP0/OBP1), where each index occupies two bits.
+fn palette_shade(palette: u8, color_index: u8) -> u8 {
+    (palette >> (color_index * 2)) & 0b11
+}
+
+// The classic green-tinted DMG LCD palette, from lightest to darkest shade.
+const DMG_GREEN_PALETTE: [(u8, u8, u8, u8); 4] = [
+    (0x9b, 0xbc, 0x0f, 0xff),
+    (0x8b, 0xac, 0x0f, 0xff),
+    (0x30, 0x62, 0x30, 0xff),
+    (0x0f, 0x38, 0x0f, 0xff),
+];
+
+// Precomputes a gamma/contrast curve that lifts blacks and compresses
+// highlights, approximating the washed-out look of a real DMG LCD panel.
+fn build_color_lut() -> [u8; 256] {
+    let mut lut = [0u8; 256];
+
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let x = i as f32 / 255.0;
+        let corrected = x.powf(0.9) * 0.85 + 0.08;
+        *entry = (corrected.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+
+    lut
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::screen::HeadlessScreen;
+
+    #[test]
+    fn display_pushes_a_full_frame_to_a_headless_screen() {
+        let memory = Rc::new(RefCell::new(Memory::new()));
+        let mut gpu = Gpu::new(memory);
+        gpu.lcdc = LCDC_LCD_ENABLE | LCDC_BG_WINDOW_ENABLE;
+
+        let mut screen = HeadlessScreen::new();
+        let mut buffer = Buffer::new();
+
+        let mut remaining = FRAME_DOTS;
+        while remaining > 0 {
+            let step = remaining.min(u32::from(u8::MAX)) as u8;
+            gpu.display(step, &mut screen, &mut buffer);
+            remaining -= u32::from(step);
+        }
+
+        assert_eq!(screen.width, VISIBLE_WIDTH);
+        assert_eq!(screen.height, VISIBLE_HEIGHT);
+        assert_eq!(
+            screen.frame.len(),
+            (VISIBLE_WIDTH * VISIBLE_HEIGHT) as usize * BYTES_PER_PIXEL as usize
+        );
+
+        // With LCDC/BGP both zeroed (besides the enable bits) and a blank
+        // tile map, every BG pixel resolves to shade 0, the lightest entry
+        // of the default palette.
+        let (r, g, b, a) = gpu.shade_to_rgba(0);
+        assert_eq!(&screen.frame[0..4], &[r, g, b, a][..]);
+    }
+
+    #[test]
+    fn coincidence_flag_requests_lcd_stat_interrupt_when_ly_equals_lyc() {
+        let memory = Rc::new(RefCell::new(Memory::new()));
+        let mut gpu = Gpu::new(memory.clone());
+        gpu.lcdc = LCDC_LCD_ENABLE;
+        gpu.lyc = 2;
+        gpu.stat = STAT_COINCIDENCE_INT;
+
+        let mut screen = HeadlessScreen::new();
+        let mut buffer = Buffer::new();
+
+        // Drive two full scanlines so `ly` advances from 0 to 2, matching
+        // `lyc`.
+        for _ in 0..2 {
+            let mut remaining = SCANLINE_DOTS;
+            while remaining > 0 {
+                let step = remaining.min(u32::from(u8::MAX)) as u8;
+                gpu.display(step, &mut screen, &mut buffer);
+                remaining -= u32::from(step);
+            }
+        }
+
+        assert_eq!(gpu.ly, 2);
+        assert_eq!(gpu.stat & STAT_COINCIDENCE_FLAG, STAT_COINCIDENCE_FLAG);
+        assert_eq!(memory.borrow().load(IF_ADDR) & IF_LCD_STAT, IF_LCD_STAT);
+    }
+
+    #[test]
+    fn render_sprites_caps_at_ten_per_line_in_oam_order() {
+        let memory = Rc::new(RefCell::new(Memory::new()));
+        let mut gpu = Gpu::new(memory.clone());
+        gpu.lcdc = LCDC_LCD_ENABLE | LCDC_OBJ_ENABLE;
+
+        // A solid 8x8 tile (color index 1 on every pixel).
+        {
+            let mut memory = memory.borrow_mut();
+            for row in 0..8usize {
+                memory.store(0x8000 + 0x10 + row * 2, 0xff);
+                memory.store(0x8000 + 0x10 + row * 2 + 1, 0x00);
+            }
+        }
+
+        // 11 non-overlapping sprites on the same scanline; only the first
+        // 10 (in OAM order) should be drawn.
+        for i in 0..11u16 {
+            let entry = OAM_ADDR + i as usize * 4;
+            let mut memory = memory.borrow_mut();
+            memory.store(entry, 16); // sprite top = ly 0
+            memory.store(entry + 1, 8 + i as u8 * 8);
+            memory.store(entry + 2, 1);
+            memory.store(entry + 3, 0);
+        }
+
+        let mut buffer = Buffer::new();
+        gpu.render_sprites(0, &mut buffer);
+
+        let expected = gpu.shade_to_rgba(palette_shade(gpu.obp0, 1));
+
+        for i in 0..10usize {
+            let pixel = i * 8;
+            let buf_index = pixel * BYTES_PER_PIXEL as usize;
+            let drawn = &buffer.buffer[buf_index..buf_index + 4];
+            assert_eq!(
+                drawn,
+                &[expected.0, expected.1, expected.2, expected.3][..],
+                "sprite {i} (within the cap) should have been drawn"
+            );
+        }
+
+        let eleventh_pixel = 10 * 8;
+        let buf_index = eleventh_pixel * BYTES_PER_PIXEL as usize;
+        assert_eq!(
+            &buffer.buffer[buf_index..buf_index + 4],
+            &[0, 0, 0, 0][..],
+            "the 11th sprite exceeds the per-line cap and should not have been drawn"
+        );
+    }
+
+    #[test]
+    fn render_sprites_breaks_x_ties_by_oam_index() {
+        let memory = Rc::new(RefCell::new(Memory::new()));
+        let mut gpu = Gpu::new(memory.clone());
+        gpu.lcdc = LCDC_LCD_ENABLE | LCDC_OBJ_ENABLE;
+        gpu.obp0 = 0b00_00_11_00; // color index 1 -> shade 3
+        gpu.obp1 = 0b00_00_01_00; // color index 1 -> shade 1
+
+        {
+            let mut memory = memory.borrow_mut();
+            for row in 0..8usize {
+                memory.store(0x8000 + 0x10 + row * 2, 0xff);
+                memory.store(0x8000 + 0x10 + row * 2 + 1, 0x00);
+            }
+
+            // Two sprites at the same X; OAM index 0 uses OBP1, index 1 uses
+            // OBP0. The lower OAM index should win the tie and be drawn.
+            memory.store(OAM_ADDR, 16);
+            memory.store(OAM_ADDR + 1, 8);
+            memory.store(OAM_ADDR + 2, 1);
+            memory.store(OAM_ADDR + 3, OAM_PALETTE);
+
+            memory.store(OAM_ADDR + 4, 16);
+            memory.store(OAM_ADDR + 5, 8);
+            memory.store(OAM_ADDR + 6, 1);
+            memory.store(OAM_ADDR + 7, 0);
+        }
+
+        let mut buffer = Buffer::new();
+        gpu.render_sprites(0, &mut buffer);
+
+        let expected = gpu.shade_to_rgba(palette_shade(gpu.obp1, 1));
+        assert_eq!(&buffer.buffer[0..4], &[expected.0, expected.1, expected.2, expected.3][..]);
+    }
+
+    #[test]
+    fn window_current_y_only_advances_on_scanlines_where_the_window_is_drawn() {
+        let memory = Rc::new(RefCell::new(Memory::new()));
+        let mut gpu = Gpu::new(memory);
+        gpu.lcdc = LCDC_LCD_ENABLE | LCDC_BG_WINDOW_ENABLE | LCDC_WINDOW_ENABLE;
+        gpu.wy = 5;
+        gpu.wx = 7; // window starts at screen column 0
+
+        let mut buffer = Buffer::new();
+
+        for ly in 0..5u8 {
+            gpu.render_window(ly, &mut buffer);
+        }
+        assert_eq!(gpu.window_current_y, 0, "window hasn't started yet (ly < wy)");
+
+        gpu.render_window(5, &mut buffer);
+        gpu.render_window(6, &mut buffer);
+        assert_eq!(gpu.window_current_y, 2);
+    }
+
+    #[test]
+    fn get_tile_resolves_signed_and_unsigned_addressing_for_representative_tile_numbers() {
+        let memory = Rc::new(RefCell::new(Memory::new()));
+        let gpu = Gpu::new(memory.clone());
+
+        let cases: [(u8, TileDataMode, u16); 8] = [
+            (0, TileDataMode::Unsigned8000, 0x8000),
+            (127, TileDataMode::Unsigned8000, 0x8000 + 127 * 16),
+            (128, TileDataMode::Unsigned8000, 0x8000 + 128 * 16),
+            (255, TileDataMode::Unsigned8000, 0x8000 + 255 * 16),
+            (0, TileDataMode::Signed8800, 0x9000),
+            (127, TileDataMode::Signed8800, 0x9000 + 127 * 16),
+            (128, TileDataMode::Signed8800, 0x8800),
+            (255, TileDataMode::Signed8800, 0x9000 - 16),
+        ];
+
+        for (tile_num, addressing, expected_addr) in cases {
+            let marker = tile_num ^ 0x5a;
+            memory.borrow_mut().store(expected_addr as usize, marker);
+
+            let tile = gpu.get_tile(tile_num, addressing);
+            assert_eq!(
+                tile[0], marker,
+                "tile {tile_num} under {addressing:?} should resolve to 0x{expected_addr:04x}"
+            );
+        }
+    }
+}
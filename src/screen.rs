@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use sdl2::render::{Canvas, Texture};
+use sdl2::video::Window;
+
+// A rendering sink for completed frames, decoupling the `Gpu` from any
+// particular presentation backend.
+pub trait Screen {
+    // Receives a completed RGBA8888 frame of the given dimensions.
+    fn put_frame(&mut self, rgba: &[u8], width: u32, height: u32);
+
+    // Presents the most recently received frame.
+    fn present(&mut self);
+}
+
+// Presents frames through an SDL2 canvas/texture pair.
+pub struct Sdl2Screen<'tc> {
+    canvas: Canvas<Window>,
+    texture: Texture<'tc>,
+}
+
+impl<'tc> Sdl2Screen<'tc> {
+    pub fn new(canvas: Canvas<Window>, texture: Texture<'tc>) -> Self {
+        Self { canvas, texture }
+    }
+}
+
+impl<'tc> Screen for Sdl2Screen<'tc> {
+    fn put_frame(&mut self, rgba: &[u8], width: u32, height: u32) {
+        // `texture.update` assumes `rgba` is exactly as large as the
+        // texture itself. Since the caller now hands us an already-cropped
+        // frame, a texture created at the wrong size would silently corrupt
+        // the picture instead of failing loudly, so check it here.
+        let query = self.texture.query();
+        assert_eq!(
+            (query.width, query.height),
+            (width, height),
+            "Sdl2Screen's texture is {}x{} but received a {}x{} frame; recreate the texture at the new size",
+            query.width,
+            query.height,
+            width,
+            height
+        );
+
+        self.texture
+            .update(None, rgba, width as usize * 4)
+            .unwrap();
+    }
+
+    fn present(&mut self) {
+        self.canvas.copy(&self.texture, None, None).unwrap();
+        self.canvas.present();
+    }
+}
+
+// A no-op sink that keeps the last frame around so integration tests can
+// assert on its contents without opening a window.
+#[derive(Default)]
+pub struct HeadlessScreen {
+    pub width: u32,
+    pub height: u32,
+    pub frame: Vec<u8>,
+}
+
+impl HeadlessScreen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Screen for HeadlessScreen {
+    fn put_frame(&mut self, rgba: &[u8], width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.frame = rgba.to_vec();
+    }
+
+    fn present(&mut self) {}
+}
+
+// Writes the visible frame out as a PNG on every `present()` call, for
+// dumping test ROM output to disk.
+pub struct PngScreen {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    frame: Vec<u8>,
+}
+
+impl PngScreen {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            width: 0,
+            height: 0,
+            frame: Vec::new(),
+        }
+    }
+}
+
+impl Screen for PngScreen {
+    fn put_frame(&mut self, rgba: &[u8], width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.frame = rgba.to_vec();
+    }
+
+    fn present(&mut self) {
+        if self.frame.is_empty() {
+            return;
+        }
+
+        let image = image::RgbaImage::from_raw(self.width, self.height, self.frame.clone())
+            .expect("frame buffer size must match width * height * 4");
+        image.save(&self.path).expect("failed to write screenshot");
+    }
+}